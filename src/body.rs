@@ -0,0 +1,201 @@
+// Copyright 2015 The tiny-http Contributors
+// Copyright 2015 The rust-chunked-transfer Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt;
+
+use crate::decoder::Decoder;
+use crate::io::Error as IoError;
+use crate::io::ErrorKind;
+use crate::io::Read;
+use crate::io::Result as IoResult;
+
+/// Reads an HTTP message body under whichever of the three HTTP/1.x framing
+/// mechanisms applies: a known `Content-Length`, the `chunked`
+/// transfer-coding, or reading until the connection is closed.
+///
+/// # Example
+///
+/// This example relies on `std::io::Read`, so it only runs with the `std`
+/// feature (the default) enabled.
+///
+/// ```
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use chunked_transfer::BodyDecoder;
+/// use std::io::Read;
+///
+/// let encoded = b"hello world" as &[u8];
+/// let mut decoder = BodyDecoder::length(encoded, 5);
+///
+/// let mut decoded = String::new();
+/// decoder.read_to_string(&mut decoded).unwrap();
+///
+/// assert_eq!(decoded, "hello");
+/// assert!(decoder.is_eof());
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() {}
+/// ```
+pub enum BodyDecoder<R> {
+    Length(LengthBody<R>),
+    Chunked(Decoder<R>),
+    Eof(EofBody<R>),
+}
+
+#[doc(hidden)]
+pub struct LengthBody<R> {
+    source: R,
+    remaining: u64,
+}
+
+#[doc(hidden)]
+pub struct EofBody<R> {
+    source: R,
+    eof: bool,
+}
+
+impl<R> BodyDecoder<R> where R: Read {
+    /// A body framed by a known `Content-Length` of `len` bytes.
+    pub fn length(source: R, len: u64) -> BodyDecoder<R> {
+        BodyDecoder::Length(LengthBody {
+            source,
+            remaining: len,
+        })
+    }
+
+    /// A body framed by the `chunked` transfer-coding.
+    pub fn chunked(source: R) -> BodyDecoder<R> {
+        BodyDecoder::Chunked(Decoder::new(source))
+    }
+
+    /// A body with no framing of its own, read until the source is closed.
+    pub fn eof(source: R) -> BodyDecoder<R> {
+        BodyDecoder::Eof(EofBody {
+            source,
+            eof: false,
+        })
+    }
+
+    /// Returns whether the body has been fully read, so that connection-reuse
+    /// logic can tell when it is safe to start the next message on the same
+    /// connection.
+    pub fn is_eof(&self) -> bool {
+        match *self {
+            BodyDecoder::Length(ref body) => body.remaining == 0,
+            BodyDecoder::Chunked(ref decoder) => decoder.is_finished(),
+            BodyDecoder::Eof(ref body) => body.eof,
+        }
+    }
+}
+
+impl<R> Read for BodyDecoder<R> where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        match *self {
+            BodyDecoder::Length(ref mut body) => {
+                if body.remaining == 0 || buf.is_empty() {
+                    return Ok(0);
+                }
+
+                let to_read = ::core::cmp::min(buf.len() as u64, body.remaining) as usize;
+                let read = body.source.read(&mut buf[.. to_read])?;
+
+                if read == 0 {
+                    return Err(IoError::new(ErrorKind::UnexpectedEof, PrematureEndOfBody));
+                }
+
+                body.remaining -= read as u64;
+                Ok(read)
+            }
+            BodyDecoder::Chunked(ref mut decoder) => decoder.read(buf),
+            BodyDecoder::Eof(ref mut body) => {
+                let read = body.source.read(buf)?;
+
+                if read == 0 {
+                    body.eof = true;
+                }
+
+                Ok(read)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct PrematureEndOfBody;
+
+impl fmt::Display for PrematureEndOfBody {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "Connection closed before the full Content-Length was received")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PrematureEndOfBody {
+    fn description(&self) -> &str {
+        "Connection closed before the full Content-Length was received"
+    }
+}
+
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::BodyDecoder;
+    use std::io;
+    use std::io::Read;
+
+    #[test]
+    fn test_length_body() {
+        let mut decoder = BodyDecoder::length(b"hello world" as &[u8], 5);
+
+        let mut decoded = String::new();
+        decoder.read_to_string(&mut decoded).unwrap();
+
+        assert_eq!(decoded, "hello");
+        assert!(decoder.is_eof());
+    }
+
+    #[test]
+    fn test_length_body_premature_eof() {
+        let mut decoder = BodyDecoder::length(b"hi" as &[u8], 5);
+
+        let mut decoded = String::new();
+        assert!(decoder.read_to_string(&mut decoded).is_err());
+    }
+
+    #[test]
+    fn test_chunked_body() {
+        let source = io::Cursor::new(b"3\r\nhel\r\n0\r\n\r\n".to_vec());
+        let mut decoder = BodyDecoder::chunked(source);
+
+        let mut decoded = String::new();
+        decoder.read_to_string(&mut decoded).unwrap();
+
+        assert_eq!(decoded, "hel");
+        assert!(decoder.is_eof());
+    }
+
+    #[test]
+    fn test_eof_body() {
+        let mut decoder = BodyDecoder::eof(b"hello world" as &[u8]);
+
+        assert!(!decoder.is_eof());
+
+        let mut decoded = String::new();
+        decoder.read_to_string(&mut decoded).unwrap();
+
+        assert_eq!(decoded, "hello world");
+        assert!(decoder.is_eof());
+    }
+}
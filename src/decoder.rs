@@ -14,18 +14,31 @@
 // limitations under the License.
 
 
-use std::io::Result as IoResult;
-use std::io::Read;
-use std::io::Error as IoError;
-use std::io::ErrorKind;
-use std::fmt;
-use std::error::Error;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::io::Error as IoError;
+use crate::io::ErrorKind;
+use crate::io::Read;
+use crate::io::Result as IoResult;
+
+// size of the internal buffer used to read from the source in blocks of up
+// to this many bytes instead of one syscall per byte while parsing chunk
+// framing
+const INPUT_BUFFER_SIZE: usize = 8 * 1024;
 
 /// Reads HTTP chunks and sends back real data.
 ///
 /// # Example
 ///
+/// This example relies on `std::io::Read`, so it only runs with the `std`
+/// feature (the default) enabled.
+///
 /// ```
+/// # #[cfg(feature = "std")]
+/// # fn main() {
 /// use chunked_transfer::Decoder;
 /// use std::io::Read;
 ///
@@ -33,123 +46,541 @@ use std::error::Error;
 /// let mut decoded = String::new();
 ///
 /// let mut decoder = Decoder::new(encoded as &[u8]);
-/// decoder.read_to_string(&mut decoded);
+/// decoder.read_to_string(&mut decoded).unwrap();
 ///
 /// assert_eq!(decoded, "hello world!!!");
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() {}
 /// ```
 pub struct Decoder<R> {
     // where the chunks come from
     source: R,
 
-    // remaining size of the chunk being read
-    // none if we are not in a chunk
-    remaining_chunks_size: Option<usize>,
+    // current position in the chunk-parsing state machine
+    state: ChunkedState,
+
+    // remaining size of the chunk being read, valid while `state` is `Body`
+    // or one of the states leading up to it
+    remaining: u64,
+
+    // internal buffer of bytes read from the source but not yet consumed by
+    // the state machine
+    buffer: Vec<u8>,
+    buffer_pos: usize,
+    buffer_len: usize,
+
+    // raw bytes of the chunk extension (if any) of the chunk currently being
+    // read, from the leading `;` up to but excluding the terminating CRLF
+    extension: Vec<u8>,
+
+    // number of hex digits seen so far for the chunk size currently being
+    // parsed, used to reject an empty size token
+    size_digits: u32,
+
+    // optional caps on the size of a single chunk and on the total number of
+    // decoded body bytes, to bound memory use when talking to an untrusted
+    // peer
+    max_chunk_size: Option<u64>,
+    max_total_size: Option<u64>,
+    total_decoded: u64,
+
+    // name/value of the trailer header currently being parsed
+    current_trailer_name: Vec<u8>,
+    current_trailer_value: Vec<u8>,
+
+    // trailer headers collected after the terminating zero-size chunk,
+    // available through `trailers()` once `read` has returned `Ok(0)`
+    trailers: Vec<(String, String)>,
+}
+
+// The states of the chunked transfer-coding parser, modeled after the state
+// machine used by hyper and actix-web. Framing is parsed one byte at a time
+// so that a `read` which only has a partial chunk header or trailer
+// available can be resumed cleanly on the next call instead of failing or
+// blocking on a full line.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ChunkedState {
+    Size,
+    SizeLws,
+    Extension,
+    ExtensionQuotedString,
+    ExtensionQuotedPair,
+    SizeLf,
+    Body,
+    BodyCr,
+    BodyLf,
+    TrailerStart,
+    TrailerName,
+    TrailerValueLws,
+    TrailerValue,
+    TrailerValueCr,
+    EndLf,
+    End,
+}
+
+// Appends `byte` to `buf`, rejecting it rather than growing past `limit` (if
+// any). Used to keep chunk extensions and trailer header lines from being
+// buffered without bound, the same way `max_chunk_size` already bounds the
+// declared chunk size in `read_size_lf`.
+fn push_bounded(buf: &mut Vec<u8>, byte: u8, limit: Option<u64>) -> IoResult<()> {
+    if let Some(limit) = limit {
+        if buf.len() as u64 >= limit {
+            return Err(IoError::new(ErrorKind::InvalidInput, DecoderError));
+        }
+    }
+
+    buf.push(byte);
+    Ok(())
 }
 
 impl<R> Decoder<R> where R: Read {
     pub fn new(source: R) -> Decoder<R> {
         Decoder {
-            source: source,
-            remaining_chunks_size: None,
+            source,
+            state: ChunkedState::Size,
+            remaining: 0,
+            buffer: vec![0; INPUT_BUFFER_SIZE],
+            buffer_pos: 0,
+            buffer_len: 0,
+            extension: Vec::new(),
+            size_digits: 0,
+            max_chunk_size: None,
+            max_total_size: None,
+            total_decoded: 0,
+            current_trailer_name: Vec::new(),
+            current_trailer_value: Vec::new(),
+            trailers: Vec::new(),
         }
     }
 
-    fn read_chunk_size(&mut self) -> Result<usize, IoError> {
-        let mut chunk_size = Vec::new();
+    /// Creates a decoder that additionally enforces a maximum size for any
+    /// single chunk and/or a maximum total number of decoded body bytes,
+    /// returning an error from `read` instead of exceeding them. Either
+    /// limit can be disabled by passing `None`.
+    pub fn with_limits(source: R, max_chunk_size: Option<u64>, max_total_size: Option<u64>) -> Decoder<R> {
+        Decoder {
+            max_chunk_size,
+            max_total_size,
+            .. Decoder::new(source)
+        }
+    }
 
-        loop {
-            let byte = match self.source.by_ref().bytes().next() {
-                Some(b) => try!(b),
-                None => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
-            };
+    /// Returns the raw bytes of the chunk extension (everything from the
+    /// leading `;` up to but excluding the terminating CRLF) of the chunk
+    /// currently or most recently being read. Empty if the chunk had no
+    /// extension.
+    pub fn extension(&self) -> &[u8] {
+        &self.extension
+    }
 
-            if byte == b'\r' {
-                break;
+    /// Returns the trailer headers sent after the terminating zero-size
+    /// chunk, in the order they appeared. Each header is added as soon as
+    /// its line has been parsed; the list is complete once `read` has
+    /// returned `Ok(0)`.
+    pub fn trailers(&self) -> &[(String, String)] {
+        &self.trailers
+    }
+
+    // Whether the entire chunked body, including the trailer section, has
+    // been consumed. Used by `BodyDecoder::is_eof`.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.state == ChunkedState::End
+    }
+
+    // Returns the next unconsumed byte from the source, refilling the
+    // internal buffer if it is empty. Returns `None` on EOF.
+    fn peek_byte(&mut self) -> IoResult<Option<u8>> {
+        if self.buffer_pos == self.buffer_len {
+            self.buffer_len = self.source.read(&mut self.buffer)?;
+            self.buffer_pos = 0;
+
+            if self.buffer_len == 0 {
+                return Ok(None);
             }
+        }
 
-            chunk_size.push(byte);
+        Ok(Some(self.buffer[self.buffer_pos]))
+    }
+
+    fn consume_byte(&mut self) {
+        self.buffer_pos += 1;
+    }
+
+    // Drives the state machine forward by one byte. Only called while
+    // `state` is not `Body` or `End`, as those are handled directly by
+    // `read`.
+    fn advance(&mut self) -> IoResult<()> {
+        let byte = match self.peek_byte()? {
+            Some(byte) => byte,
+            None => return Err(IoError::new(ErrorKind::UnexpectedEof, DecoderError)),
+        };
+
+        match self.state {
+            ChunkedState::Size => self.read_size(byte),
+            ChunkedState::SizeLws => self.read_size_lws(byte),
+            ChunkedState::Extension => self.read_extension(byte),
+            ChunkedState::ExtensionQuotedString => self.read_extension_quoted_string(byte),
+            ChunkedState::ExtensionQuotedPair => self.read_extension_quoted_pair(byte),
+            ChunkedState::SizeLf => self.read_size_lf(byte),
+            ChunkedState::BodyCr => self.read_body_cr(byte),
+            ChunkedState::BodyLf => self.read_body_lf(byte),
+            ChunkedState::TrailerStart => self.read_trailer_start(byte),
+            ChunkedState::TrailerName => self.read_trailer_name(byte),
+            ChunkedState::TrailerValueLws => self.read_trailer_value_lws(byte),
+            ChunkedState::TrailerValue => self.read_trailer_value(byte),
+            ChunkedState::TrailerValueCr => self.read_trailer_value_cr(byte),
+            ChunkedState::EndLf => self.read_end_lf(byte),
+            ChunkedState::Body | ChunkedState::End => unreachable!(),
         }
+    }
+
+    // Folds one more hex digit into `remaining`, rejecting a size that would
+    // overflow a `u64` rather than silently wrapping or truncating it.
+    fn accumulate_size_digit(&mut self, digit: u8) -> IoResult<()> {
+        self.size_digits += 1;
 
-        match self.source.by_ref().bytes().next() {
-            Some(Ok(b'\n')) => (),
+        self.remaining = match self.remaining.checked_mul(16).and_then(|v| v.checked_add(digit as u64)) {
+            Some(remaining) => remaining,
+            None => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+        };
+
+        Ok(())
+    }
+
+    fn read_size(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b'0' ..= b'9' => {
+                self.consume_byte();
+                self.accumulate_size_digit(byte - b'0')?;
+            }
+            b'a' ..= b'f' => {
+                self.consume_byte();
+                self.accumulate_size_digit(byte - b'a' + 10)?;
+            }
+            b'A' ..= b'F' => {
+                self.consume_byte();
+                self.accumulate_size_digit(byte - b'A' + 10)?;
+            }
+            b';' if self.size_digits > 0 => {
+                self.consume_byte();
+                self.extension.clear();
+                push_bounded(&mut self.extension, byte, self.max_chunk_size)?;
+                self.state = ChunkedState::Extension;
+            }
+            b' ' | b'\t' if self.size_digits > 0 => {
+                self.consume_byte();
+                self.state = ChunkedState::SizeLws;
+            }
+            b'\r' if self.size_digits > 0 => {
+                self.consume_byte();
+                self.extension.clear();
+                self.state = ChunkedState::SizeLf;
+            }
+            // rejects an empty size token as well as stray leading
+            // characters such as `+` or a `0x` prefix, which `from_str_radix`
+            // would otherwise have accepted or silently misparsed
             _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
         }
 
-        let chunk_size = match String::from_utf8(chunk_size) {
-            Ok(c) => c,
-            Err(_) => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError))
-        };
+        Ok(())
+    }
 
-        let chunk_size = match usize::from_str_radix(&chunk_size, 16) {
-            Ok(c) => c,
-            Err(_) => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError))
+    // optional whitespace between the size and either a `;` extension or the
+    // terminating CRLF
+    fn read_size_lws(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b' ' | b'\t' => self.consume_byte(),
+            b';' => {
+                self.consume_byte();
+                self.extension.clear();
+                push_bounded(&mut self.extension, byte, self.max_chunk_size)?;
+                self.state = ChunkedState::Extension;
+            }
+            b'\r' => {
+                self.consume_byte();
+                self.extension.clear();
+                self.state = ChunkedState::SizeLf;
+            }
+            _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+        }
+
+        Ok(())
+    }
+
+    // chunk-ext-name / chunk-ext-val (token form), terminated by the CRLF
+    // that ends the chunk-size line
+    fn read_extension(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b'"' => {
+                self.consume_byte();
+                push_bounded(&mut self.extension, byte, self.max_chunk_size)?;
+                self.state = ChunkedState::ExtensionQuotedString;
+            }
+            b'\r' => {
+                self.consume_byte();
+                self.state = ChunkedState::SizeLf;
+            }
+            _ => {
+                self.consume_byte();
+                push_bounded(&mut self.extension, byte, self.max_chunk_size)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // chunk-ext-val as a quoted-string; a `;` or `\r` inside the quotes does
+    // not end the extension
+    fn read_extension_quoted_string(&mut self, byte: u8) -> IoResult<()> {
+        self.consume_byte();
+        push_bounded(&mut self.extension, byte, self.max_chunk_size)?;
+
+        self.state = match byte {
+            b'\\' => ChunkedState::ExtensionQuotedPair,
+            b'"' => ChunkedState::Extension,
+            _ => ChunkedState::ExtensionQuotedString,
         };
 
-        Ok(chunk_size)
+        Ok(())
     }
-}
 
-impl<R> Read for Decoder<R> where R: Read {
-    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
-        let remaining_chunks_size = match self.remaining_chunks_size {
-            Some(c) => c,
-            None => {
-                // first possibility: we are not in a chunk, so we'll attempt to determine
-                // the chunks size
-                let chunk_size = try!(self.read_chunk_size());
-
-                // if the chunk size is 0, we are at EOF
-                if chunk_size == 0 {
-                    match self.source.by_ref().bytes().next() {
-                        Some(Ok(b'\r')) => (),
-                        _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
-                    }
+    // the character following a `\` inside a quoted-string is taken
+    // literally, even if it is a `"`, `;` or `\r`
+    fn read_extension_quoted_pair(&mut self, byte: u8) -> IoResult<()> {
+        self.consume_byte();
+        push_bounded(&mut self.extension, byte, self.max_chunk_size)?;
+        self.state = ChunkedState::ExtensionQuotedString;
 
-                    match self.source.by_ref().bytes().next() {
-                        Some(Ok(b'\n')) => (),
-                        _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
-                    }
+        Ok(())
+    }
 
-                    return Ok(0);
+    fn read_size_lf(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b'\n' => {
+                self.consume_byte();
+                self.size_digits = 0;
+
+                if self.remaining == 0 {
+                    self.state = ChunkedState::TrailerStart;
+                } else {
+                    if let Some(max_chunk_size) = self.max_chunk_size {
+                        if self.remaining > max_chunk_size {
+                            return Err(IoError::new(ErrorKind::InvalidInput, DecoderError));
+                        }
+                    }
+                    self.state = ChunkedState::Body;
                 }
+            }
+            _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+        }
 
-                // now that we now the current chunk size, calling ourselves recursively
-                self.remaining_chunks_size = Some(chunk_size);
-                return self.read(buf);
+        Ok(())
+    }
+
+    fn read_body_cr(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b'\r' => {
+                self.consume_byte();
+                self.state = ChunkedState::BodyLf;
             }
-        };
+            _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+        }
+
+        Ok(())
+    }
 
-        // second possibility: we continue reading from a chunk
-        if buf.len() < remaining_chunks_size {
-            let read = try!(self.source.read(buf));
-            self.remaining_chunks_size = Some(remaining_chunks_size - read);
-            return Ok(read);
+    fn read_body_lf(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b'\n' => {
+                self.consume_byte();
+                self.state = ChunkedState::Size;
+            }
+            _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
         }
 
-        // third possibility: the read request goes further than the current chunk
-        // we simply read until the end of the chunk and return
-        assert!(buf.len() >= remaining_chunks_size);
+        Ok(())
+    }
+
+    // after the terminating zero-size chunk: either the blank CRLF that ends
+    // the trailer section, or the first byte of a trailer header name
+    fn read_trailer_start(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b'\r' => {
+                self.consume_byte();
+                self.state = ChunkedState::EndLf;
+            }
+            b':' => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+            _ => {
+                self.consume_byte();
+                push_bounded(&mut self.current_trailer_name, byte, self.max_chunk_size)?;
+                self.state = ChunkedState::TrailerName;
+            }
+        }
 
-        let buf = &mut buf[.. remaining_chunks_size];
-        let read = try!(self.source.read(buf));
+        Ok(())
+    }
 
-        self.remaining_chunks_size = if read == remaining_chunks_size {
-            match self.source.by_ref().bytes().next() {
-                Some(Ok(b'\r')) => (),
-                _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+    fn read_trailer_name(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b':' => {
+                self.consume_byte();
+                self.state = ChunkedState::TrailerValueLws;
             }
+            b'\r' | b'\n' => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+            _ => {
+                self.consume_byte();
+                push_bounded(&mut self.current_trailer_name, byte, self.max_chunk_size)?;
+            }
+        }
+
+        Ok(())
+    }
 
-            match self.source.by_ref().bytes().next() {
-                Some(Ok(b'\n')) => (),
-                _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+    // optional whitespace between the `:` and the trailer value
+    fn read_trailer_value_lws(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b' ' | b'\t' => self.consume_byte(),
+            b'\r' => {
+                self.consume_byte();
+                self.state = ChunkedState::TrailerValueCr;
             }
+            _ => {
+                self.consume_byte();
+                push_bounded(&mut self.current_trailer_value, byte, self.max_chunk_size)?;
+                self.state = ChunkedState::TrailerValue;
+            }
+        }
 
-            None
+        Ok(())
+    }
+
+    fn read_trailer_value(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b'\r' => {
+                self.consume_byte();
+                self.state = ChunkedState::TrailerValueCr;
+            }
+            _ => {
+                self.consume_byte();
+                push_bounded(&mut self.current_trailer_value, byte, self.max_chunk_size)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_trailer_value_cr(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b'\n' => {
+                self.consume_byte();
+
+                let name = match String::from_utf8(::core::mem::take(&mut self.current_trailer_name)) {
+                    Ok(name) => name,
+                    Err(_) => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+                };
+                let value = match String::from_utf8(::core::mem::take(&mut self.current_trailer_value)) {
+                    Ok(value) => value,
+                    Err(_) => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+                };
+
+                self.trailers.push((name, value));
+                self.state = ChunkedState::TrailerStart;
+            }
+            _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+        }
+
+        Ok(())
+    }
+
+    fn read_end_lf(&mut self, byte: u8) -> IoResult<()> {
+        match byte {
+            b'\n' => {
+                self.consume_byte();
+                self.state = ChunkedState::End;
+            }
+            _ => return Err(IoError::new(ErrorKind::InvalidInput, DecoderError)),
+        }
+
+        Ok(())
+    }
+
+    // Copies as much of the current chunk's body as will fit in `buf`,
+    // pulling first from whatever is left in the internal buffer and then
+    // reading directly from the source. Transitions to `BodyCr` once
+    // `remaining` reaches zero. Returns `Ok(0)` without reading anything if
+    // the chunk is already empty.
+    fn read_body(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let to_copy = ::core::cmp::min(buf.len() as u64, self.remaining);
+
+        if to_copy == 0 {
+            self.state = ChunkedState::BodyCr;
+            return Ok(0);
+        }
+
+        if let Some(max_total_size) = self.max_total_size {
+            if self.total_decoded.saturating_add(to_copy) > max_total_size {
+                return Err(IoError::new(ErrorKind::InvalidInput, DecoderError));
+            }
+        }
+
+        let to_copy = to_copy as usize;
+
+        let read = if self.buffer_pos < self.buffer_len {
+            let available = ::core::cmp::min(to_copy, self.buffer_len - self.buffer_pos);
+            buf[.. available].copy_from_slice(&self.buffer[self.buffer_pos .. self.buffer_pos + available]);
+            self.buffer_pos += available;
+            available
         } else {
-            Some(remaining_chunks_size - read)
+            self.source.read(&mut buf[.. to_copy])?
         };
 
-        return Ok(read);
+        if read == 0 {
+            return Err(IoError::new(ErrorKind::UnexpectedEof, DecoderError));
+        }
+
+        self.remaining -= read as u64;
+        self.total_decoded += read as u64;
+
+        if self.remaining == 0 {
+            self.state = ChunkedState::BodyCr;
+        }
+
+        Ok(read)
+    }
+}
+
+impl<R> Read for Decoder<R> where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let mut copied = 0;
+
+        while copied < buf.len() {
+            if self.state == ChunkedState::End {
+                break;
+            }
+
+            // once body bytes have been copied, return them instead of going
+            // back to the source, so a source that fails mid-frame cannot
+            // make them be lost along with the error
+            if copied > 0 && self.buffer_pos == self.buffer_len {
+                break;
+            }
+
+            if self.state == ChunkedState::Body {
+                let read = self.read_body(&mut buf[copied ..])?;
+                if read == 0 {
+                    // chunk body fully copied; `read_body` already moved us
+                    // into `BodyCr` to consume the trailing CRLF
+                    continue;
+                }
+                copied += read;
+                continue;
+            }
+
+            self.advance()?;
+        }
+
+        Ok(copied)
     }
 }
 
@@ -162,14 +593,15 @@ impl fmt::Display for DecoderError {
     }
 }
 
-impl Error for DecoderError {
+#[cfg(feature = "std")]
+impl std::error::Error for DecoderError {
     fn description(&self) -> &str {
         "Error while decoding chunks"
     }
 }
 
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod test {
     use super::Decoder;
     use std::io;
@@ -210,7 +642,7 @@ mod test {
         let mut decoded = Decoder::new(source);
 
         let mut string = String::new();
-        decoded.read_to_string(&mut string).is_err();
+        assert!(decoded.read_to_string(&mut string).is_err());
     }
 
     #[test]
@@ -219,6 +651,225 @@ mod test {
         let mut decoded = Decoder::new(source);
 
         let mut string = String::new();
-        decoded.read_to_string(&mut string).is_err();
+        assert!(decoded.read_to_string(&mut string).is_err());
+    }
+
+    // A reader that only ever returns a single byte per call, used to
+    // exercise the decoder's ability to resume mid-frame when the source
+    // yields short reads instead of whole chunks at a time.
+    struct OneByteAtATime<'a> {
+        data: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Read for OneByteAtATime<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pos >= self.data.len() || buf.is_empty() {
+                return Ok(0);
+            }
+
+            buf[0] = self.data[self.pos];
+            self.pos += 1;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_decode_with_short_reads() {
+        let data = b"3\r\nhel\r\nb\r\nlo world!!!\r\n0\r\n\r\n";
+        let source = OneByteAtATime { data, pos: 0 };
+        let mut decoder = Decoder::new(source);
+
+        let mut decoded = String::new();
+        decoder.read_to_string(&mut decoded).unwrap();
+
+        assert_eq!(decoded, "hello world!!!");
+    }
+
+    #[test]
+    fn test_decode_small_buffer_reads() {
+        // reads the body in pieces smaller than a full chunk to make sure
+        // state correctly resumes across `read` calls
+        let source = io::Cursor::new(b"5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n".to_vec());
+        let mut decoder = Decoder::new(source);
+
+        let mut decoded = Vec::new();
+        let mut buf = [0u8; 3];
+        loop {
+            let read = decoder.read(&mut buf).unwrap();
+            if read == 0 {
+                break;
+            }
+            decoded.extend_from_slice(&buf[.. read]);
+        }
+
+        assert_eq!(decoded, b"hello world");
+    }
+
+    #[test]
+    fn test_decode_with_chunk_extension() {
+        let mut decoder = Decoder::new(b"3;name=value\r\nhel\r\n0\r\n\r\n" as &[u8]);
+
+        let mut decoded = String::new();
+        decoder.read_to_string(&mut decoded).unwrap();
+
+        assert_eq!(decoded, "hel");
+    }
+
+    #[test]
+    fn test_decode_with_quoted_chunk_extension() {
+        // the quoted extension value contains a `;` and an escaped `"`,
+        // neither of which should be mistaken for the end of the extension
+        let mut decoder = Decoder::new(b"3;name=\"a;b\\\"c\"\r\nhel\r\n0\r\n\r\n" as &[u8]);
+
+        let mut decoded = String::new();
+        decoder.read_to_string(&mut decoded).unwrap();
+
+        assert_eq!(decoded, "hel");
+    }
+
+    #[test]
+    fn test_extension_accessor() {
+        let mut decoder = Decoder::new(b"3;name=value\r\nhel\r\n0\r\n\r\n" as &[u8]);
+
+        // read exactly the first chunk's body so the terminating zero-size
+        // chunk (which has no extension) hasn't been parsed yet
+        let mut buf = [0u8; 3];
+        decoder.read_exact(&mut buf).unwrap();
+
+        assert_eq!(&buf, b"hel");
+        assert_eq!(decoder.extension(), b";name=value");
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_hex_chunk_size() {
+        // 17 hex digits cannot fit in a u64
+        let mut decoder = Decoder::new(b"fffffffffffffffff\r\n\r\n" as &[u8]);
+
+        let mut decoded = Vec::new();
+        assert!(decoder.read_to_end(&mut decoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_empty_chunk_size() {
+        let mut decoder = Decoder::new(b"\r\nhel\r\n0\r\n\r\n" as &[u8]);
+
+        let mut decoded = Vec::new();
+        assert!(decoder.read_to_end(&mut decoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_space_in_chunk_size() {
+        let mut decoder = Decoder::new(b"3 3\r\nhel\r\n0\r\n\r\n" as &[u8]);
+
+        let mut decoded = Vec::new();
+        assert!(decoder.read_to_end(&mut decoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_nul_in_chunk_size() {
+        let mut decoder = Decoder::new(b"3\x003\r\nhel\r\n0\r\n\r\n" as &[u8]);
+
+        let mut decoded = Vec::new();
+        assert!(decoder.read_to_end(&mut decoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_plus_prefixed_chunk_size() {
+        let mut decoder = Decoder::new(b"+3\r\nhel\r\n0\r\n\r\n" as &[u8]);
+
+        let mut decoded = Vec::new();
+        assert!(decoder.read_to_end(&mut decoded).is_err());
+    }
+
+    #[test]
+    fn test_decode_rejects_0x_prefixed_chunk_size() {
+        let mut decoder = Decoder::new(b"0x3\r\nhel\r\n0\r\n\r\n" as &[u8]);
+
+        let mut decoded = Vec::new();
+        assert!(decoder.read_to_end(&mut decoded).is_err());
+    }
+
+    #[test]
+    fn test_max_chunk_size_limit() {
+        let source = b"a\r\n0123456789\r\n0\r\n\r\n" as &[u8];
+        let mut decoder = Decoder::with_limits(source, Some(5), None);
+
+        let mut decoded = Vec::new();
+        assert!(decoder.read_to_end(&mut decoded).is_err());
+    }
+
+    #[test]
+    fn test_max_total_size_limit() {
+        let source = b"3\r\nhel\r\n3\r\nlo!\r\n0\r\n\r\n" as &[u8];
+        let mut decoder = Decoder::with_limits(source, None, Some(4));
+
+        let mut decoded = Vec::new();
+        assert!(decoder.read_to_end(&mut decoded).is_err());
+    }
+
+    #[test]
+    fn test_limits_allow_conforming_input() {
+        let source = b"3\r\nhel\r\nb\r\nlo world!!!\r\n0\r\n\r\n" as &[u8];
+        let mut decoder = Decoder::with_limits(source, Some(20), Some(20));
+
+        let mut decoded = String::new();
+        decoder.read_to_string(&mut decoded).unwrap();
+
+        assert_eq!(decoded, "hello world!!!");
+    }
+
+    #[test]
+    fn test_max_chunk_size_limit_bounds_extension_growth() {
+        // an extension that never terminates shouldn't be buffered past the
+        // configured limit while waiting for a CRLF that never arrives
+        let mut source = b"3;".to_vec();
+        source.extend(std::iter::repeat_n(b'a', 1_000_000));
+        let mut decoder = Decoder::with_limits(&source[..], Some(100), None);
+
+        let mut decoded = Vec::new();
+        assert!(decoder.read_to_end(&mut decoded).is_err());
+        assert!(decoder.extension().len() <= 100);
+    }
+
+    #[test]
+    fn test_max_chunk_size_limit_bounds_trailer_growth() {
+        // an unterminated trailer value shouldn't be buffered past the
+        // configured limit either
+        let mut source = b"0\r\nChecksum: ".to_vec();
+        source.extend(std::iter::repeat_n(b'a', 1_000_000));
+        let mut decoder = Decoder::with_limits(&source[..], Some(100), None);
+
+        let mut decoded = Vec::new();
+        assert!(decoder.read_to_end(&mut decoded).is_err());
+        assert!(decoder.trailers().is_empty());
+    }
+
+    #[test]
+    fn test_decode_with_trailers() {
+        let source = b"3\r\nhel\r\n0\r\nChecksum: abc123\r\nX-Extra: yes\r\n\r\n" as &[u8];
+        let mut decoder = Decoder::new(source);
+
+        let mut decoded = String::new();
+        decoder.read_to_string(&mut decoded).unwrap();
+
+        assert_eq!(decoded, "hel");
+        assert_eq!(
+            decoder.trailers(),
+            &[
+                ("Checksum".to_string(), "abc123".to_string()),
+                ("X-Extra".to_string(), "yes".to_string()),
+            ][..]
+        );
+    }
+
+    #[test]
+    fn test_decode_without_trailers_has_empty_trailers() {
+        let mut decoder = Decoder::new(b"3\r\nhel\r\n0\r\n\r\n" as &[u8]);
+
+        let mut decoded = String::new();
+        decoder.read_to_string(&mut decoded).unwrap();
+
+        assert_eq!(decoder.trailers(), &[][..]);
     }
 }
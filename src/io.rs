@@ -0,0 +1,66 @@
+// Copyright 2015 The tiny-http Contributors
+// Copyright 2015 The rust-chunked-transfer Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// With the `std` feature enabled (the default) this is just `std::io`. With
+// it disabled, it's a minimal `Read`/`Error` substitute over `alloc` alone,
+// just enough for `Decoder` to compile without the standard library.
+
+#[cfg(feature = "std")]
+pub use std::io::{Read, Result, Error, ErrorKind};
+
+#[cfg(not(feature = "std"))]
+pub use self::no_std::{Read, Result, Error, ErrorKind};
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    use core::fmt;
+
+    pub type Result<T> = ::core::result::Result<T, Error>;
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum ErrorKind {
+        InvalidInput,
+        UnexpectedEof,
+        Other,
+    }
+
+    /// Stand-in for `std::io::Error` that doesn't require an allocator-backed
+    /// error source, since `core` has no equivalent of `std::error::Error`
+    /// on older compilers.
+    #[derive(Debug)]
+    pub struct Error {
+        kind: ErrorKind,
+    }
+
+    impl Error {
+        pub fn new<E>(kind: ErrorKind, _error: E) -> Error {
+            Error { kind }
+        }
+
+        pub fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmt, "{:?}", self.kind)
+        }
+    }
+
+    pub trait Read {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright 2015 The tiny-http Contributors
+// Copyright 2015 The rust-chunked-transfer Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reads the `chunked` HTTP transfer-coding, and more generally HTTP/1.x
+//! message bodies under any of the `Content-Length`, `chunked`, or
+//! read-to-EOF framings via [`BodyDecoder`].
+//!
+//! With the default `std` feature disabled, the crate builds under
+//! `#![no_std]` against `alloc` alone, for embedded or WASM targets that
+//! have an allocator but no standard library.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+extern crate core;
+extern crate alloc;
+
+mod io;
+mod decoder;
+mod body;
+
+pub use decoder::Decoder;
+pub use body::BodyDecoder;